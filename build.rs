@@ -0,0 +1,24 @@
+use std::{env, fs, path::Path};
+
+use shaderc::{Compiler, ShaderKind};
+
+fn main() {
+    println!("cargo:rerun-if-changed=shaders/gradient.comp");
+
+    let source = fs::read_to_string("shaders/gradient.comp").expect("failed to read shader source");
+
+    let compiler = Compiler::new().expect("failed to create shader compiler");
+    let binary = compiler
+        .compile_into_spirv(
+            &source,
+            ShaderKind::Compute,
+            "gradient.comp",
+            "main",
+            None,
+        )
+        .expect("failed to compile gradient.comp to SPIR-V");
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("gradient.spv"), binary.as_binary_u8())
+        .expect("failed to write compiled SPIR-V");
+}
@@ -0,0 +1,240 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use anyhow::{Context, Result};
+use ash::vk;
+use gpu_allocator::vulkan::*;
+
+use crate::vulkan_context::VulkanContext;
+
+/// Creates a buffer and binds memory for it from `context`'s allocator at
+/// the given `location`, the shared plumbing behind [`upload_buffer`] and
+/// [`download_buffer`].
+pub fn create_buffer(
+    context: &VulkanContext,
+    size: vk::DeviceSize,
+    usage: vk::BufferUsageFlags,
+    location: gpu_allocator::MemoryLocation,
+    name: &str,
+) -> Result<(vk::Buffer, Allocation)> {
+    let buffer = {
+        let create_info = vk::BufferCreateInfo::builder().size(size).usage(usage);
+        unsafe { context.device.create_buffer(&create_info, None) }.unwrap()
+    };
+
+    let allocation = {
+        let memory_requirements = unsafe { context.device.get_buffer_memory_requirements(buffer) };
+        let allocation_create_description = AllocationCreateDesc {
+            name,
+            requirements: memory_requirements,
+            location,
+            linear: true,
+            allocation_scheme: AllocationScheme::GpuAllocatorManaged,
+        };
+
+        let allocation = context
+            .allocator
+            .borrow_mut()
+            .allocate(&allocation_create_description)?;
+        unsafe {
+            context
+                .device
+                .bind_buffer_memory(buffer, allocation.memory(), allocation.offset())
+        }
+        .unwrap();
+        allocation
+    };
+
+    Ok((buffer, allocation))
+}
+
+/// Records `record` into a fresh command buffer, submits it on the
+/// context's queue, and blocks until it has finished executing. Used for
+/// the one-off transfer commands in [`upload_buffer`] and
+/// [`download_buffer`] where overlapping submissions bring no benefit.
+fn one_shot_submit(
+    context: &VulkanContext,
+    command_pool: vk::CommandPool,
+    record: impl FnOnce(vk::CommandBuffer),
+) -> Result<()> {
+    let device = &context.device;
+
+    let command_buffer = {
+        let create_info = vk::CommandBufferAllocateInfo::builder()
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_pool(command_pool)
+            .command_buffer_count(1);
+        unsafe { device.allocate_command_buffers(&create_info) }?
+            .into_iter()
+            .next()
+            .context("Allocation of Command Buffer failed!")?
+    };
+
+    {
+        let begin_info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        unsafe { device.begin_command_buffer(command_buffer, &begin_info) }?
+    }
+
+    record(command_buffer);
+
+    unsafe { device.end_command_buffer(command_buffer) }?;
+
+    let fence = {
+        let create_info = vk::FenceCreateInfo::builder().build();
+        unsafe { device.create_fence(&create_info, None) }?
+    };
+
+    let submit_info =
+        vk::SubmitInfo::builder().command_buffers(std::slice::from_ref(&command_buffer));
+    unsafe { device.queue_submit(context.queue, std::slice::from_ref(&submit_info), fence) }?;
+    unsafe { device.wait_for_fences(std::slice::from_ref(&fence), true, u64::MAX) }?;
+
+    unsafe { device.destroy_fence(fence, None) }
+    unsafe { device.free_command_buffers(command_pool, std::slice::from_ref(&command_buffer)) }
+
+    Ok(())
+}
+
+/// Uploads `data` into a fast `GpuOnly` buffer via a `CpuToGpu` staging
+/// buffer, mirroring vulkano's `ImmutableBuffer`: the GPU reads from
+/// device-local memory, while the CPU-visible copy only exists transiently
+/// for the upload itself.
+///
+/// Unused by the gradient demo today (its pixel buffer is always
+/// GPU-generated, never host-supplied), but kept as the upload half of the
+/// [`download_buffer`] pair for whatever feeds this renderer CPU-side data
+/// next (mesh/texture data, for instance).
+#[allow(dead_code)]
+pub fn upload_buffer(
+    context: &VulkanContext,
+    command_pool: vk::CommandPool,
+    usage: vk::BufferUsageFlags,
+    data: &[u8],
+) -> Result<(vk::Buffer, Allocation)> {
+    let size = data.len() as vk::DeviceSize;
+
+    let (device_buffer, device_allocation) = create_buffer(
+        context,
+        size,
+        usage | vk::BufferUsageFlags::TRANSFER_DST,
+        gpu_allocator::MemoryLocation::GpuOnly,
+        "Device-Local Buffer",
+    )?;
+
+    let (staging_buffer, mut staging_allocation) = create_buffer(
+        context,
+        size,
+        vk::BufferUsageFlags::TRANSFER_SRC,
+        gpu_allocator::MemoryLocation::CpuToGpu,
+        "Upload Staging Buffer",
+    )?;
+
+    staging_allocation
+        .mapped_slice_mut()
+        .context("Cannot Able to Access Staging Buffer from Host")?[..data.len()]
+        .copy_from_slice(data);
+
+    one_shot_submit(context, command_pool, |command_buffer| {
+        let region = vk::BufferCopy::builder().size(size).build();
+        unsafe {
+            context.device.cmd_copy_buffer(
+                command_buffer,
+                staging_buffer,
+                device_buffer,
+                std::slice::from_ref(&region),
+            )
+        }
+    })?;
+
+    context.allocator.borrow_mut().free(staging_allocation)?;
+    unsafe { context.device.destroy_buffer(staging_buffer, None) }
+
+    Ok((device_buffer, device_allocation))
+}
+
+/// Reads back `size` bytes from `buffer` via a `GpuToCpu` staging buffer,
+/// the counterpart to [`upload_buffer`] for `GpuOnly` buffers that the host
+/// cannot map directly.
+///
+/// Unused since the gradient demo moved from a PNG readback to presenting
+/// straight to a swapchain, but kept for whatever next needs to pull
+/// results back to the host (screenshotting, a readback-based test, etc.).
+#[allow(dead_code)]
+pub fn download_buffer(
+    context: &VulkanContext,
+    command_pool: vk::CommandPool,
+    buffer: vk::Buffer,
+    size: vk::DeviceSize,
+) -> Result<Vec<u8>> {
+    let (staging_buffer, mut staging_allocation) = create_buffer(
+        context,
+        size,
+        vk::BufferUsageFlags::TRANSFER_DST,
+        gpu_allocator::MemoryLocation::GpuToCpu,
+        "Download Staging Buffer",
+    )?;
+
+    one_shot_submit(context, command_pool, |command_buffer| {
+        let region = vk::BufferCopy::builder().size(size).build();
+        unsafe {
+            context.device.cmd_copy_buffer(
+                command_buffer,
+                buffer,
+                staging_buffer,
+                std::slice::from_ref(&region),
+            )
+        }
+    })?;
+
+    let data = staging_allocation
+        .mapped_slice()
+        .context("Cannot Able to Access Staging Buffer from Host")?[..size as usize]
+        .to_vec();
+
+    context.allocator.borrow_mut().free(staging_allocation)?;
+    unsafe { context.device.destroy_buffer(staging_buffer, None) }
+
+    Ok(data)
+}
+
+/// An RAII buffer: owns a `vk::Buffer` plus its [`Allocation`] and frees
+/// both on drop, so callers don't need a manual `destroy_buffer` +
+/// `allocator.free` pair at the end of `main`.
+pub struct ScopedBuffer {
+    device: ash::Device,
+    allocator: Rc<RefCell<Allocator>>,
+    buffer: vk::Buffer,
+    allocation: Option<Allocation>,
+}
+
+impl ScopedBuffer {
+    pub fn new(
+        context: &VulkanContext,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+        location: gpu_allocator::MemoryLocation,
+        name: &str,
+    ) -> Result<Self> {
+        let (buffer, allocation) = create_buffer(context, size, usage, location, name)?;
+        Ok(Self {
+            device: context.device.clone(),
+            allocator: Rc::clone(&*context.allocator),
+            buffer,
+            allocation: Some(allocation),
+        })
+    }
+
+    pub fn buffer(&self) -> vk::Buffer {
+        self.buffer
+    }
+}
+
+impl Drop for ScopedBuffer {
+    fn drop(&mut self) {
+        if let Some(allocation) = self.allocation.take() {
+            let _ = self.allocator.borrow_mut().free(allocation);
+        }
+        unsafe { self.device.destroy_buffer(self.buffer, None) }
+    }
+}
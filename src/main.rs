@@ -1,85 +1,178 @@
+mod buffer;
+mod pipeline;
+mod scoped;
+mod swapchain;
+mod vulkan_context;
+
 use anyhow::{Context, Result};
-use ash::{
-    self,
-    vk::{self, DeviceQueueCreateInfo},
-};
-use gpu_allocator::vulkan::*;
+use ash::vk;
+use winit::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop};
+use winit::platform::run_return::EventLoopExtRunReturn;
+use winit::window::WindowBuilder;
+
+use buffer::ScopedBuffer;
+use pipeline::GradientPipeline;
+use scoped::{create_fence, FrameSync, ScopedCommandPool};
+use swapchain::Swapchain;
+use vulkan_context::VulkanContext;
 
 fn main() -> Result<()> {
     // CONFIG
-    let width: u64 = 720;
-    let height: u64 = 720;
-    let value_count = width * height;
-    let value = 255 << 24;
+    let width: u32 = 720;
+    let height: u32 = 720;
+    let value_count = width as u64 * height as u64;
+
+    // WINDOW
+    //
+    // Non-resizable: the swapchain's extent, the pixel buffer and the
+    // compute dispatch are all sized for `width`x`height` once up front and
+    // never revisited, so letting the user resize the surface would need
+    // swapchain recreation (and a buffer/dispatch resize to match) that
+    // this single-shot gradient demo has no use for.
+    //
+    // `PhysicalSize`, not `LogicalSize`: a logical size is scaled by the
+    // monitor's scale factor to get the surface's actual physical extent,
+    // so on any HiDPI display (scale factor != 1.0) the swapchain would end
+    // up larger than `width`x`height` while `pixel_buffer` stays sized for
+    // exactly `width`x`height`, copying past the end of its allocation.
+    let mut event_loop = EventLoop::new();
+    let window = WindowBuilder::new()
+        .with_title("vulkanrenderer")
+        .with_inner_size(winit::dpi::PhysicalSize::new(width, height))
+        .with_resizable(false)
+        .build(&event_loop)?;
 
     // CONTEXT
-    let entry = unsafe { ash::Entry::load() }?;
+    let context = VulkanContext::new(&window)?;
+    let device = context.device.clone();
 
-    let instance = {
-        let application_info = vk::ApplicationInfo::builder().api_version(vk::API_VERSION_1_3);
-        let create_info = vk::InstanceCreateInfo::builder().application_info(&application_info);
-        unsafe { entry.create_instance(&create_info, None) }?
-    };
+    let buffer_size = value_count * std::mem::size_of::<i32>() as vk::DeviceSize;
+    let pixel_buffer = ScopedBuffer::new(
+        &context,
+        buffer_size,
+        vk::BufferUsageFlags::TRANSFER_SRC | vk::BufferUsageFlags::STORAGE_BUFFER,
+        gpu_allocator::MemoryLocation::GpuOnly,
+        "Pixel Buffer",
+    )?;
 
-    let physical_device = unsafe { instance.enumerate_physical_devices() }?
-        .into_iter()
-        .next()
-        .context("No physical device found!")?;
-
-    let device = {
-        let queue_priorities = [1.0];
-        let queue_create_info = DeviceQueueCreateInfo::builder()
-            .queue_family_index(0)
-            .queue_priorities(&queue_priorities);
-        let create_info = vk::DeviceCreateInfo::builder()
-            .queue_create_infos(std::slice::from_ref(&queue_create_info));
-        unsafe { instance.create_device(physical_device, &create_info, None) }?
-    };
+    let swapchain = Swapchain::new(&context, width, height)?;
 
-    let queue = unsafe { device.get_device_queue(0, 0) };
+    let gradient_pipeline = GradientPipeline::new(&context, &pixel_buffer)?;
 
-    // CREATING ALLOCATOR
-    let mut allocator = {
-        let allocator_create_desc = AllocatorCreateDesc {
-            instance: instance.clone(),
-            device: device.clone(),
-            physical_device,
-            debug_settings: Default::default(),
-            buffer_device_address: false,
-        };
+    let command_pool = ScopedCommandPool::new(&context)?;
 
-        Allocator::new(&allocator_create_desc)?
-    };
+    // Fill the pixel buffer once up front; the render loop below only
+    // copies it into whichever swapchain image comes back from
+    // `acquire_next_image`, since the gradient itself never changes.
+    run_gradient_compute(
+        &device,
+        command_pool.command_pool(),
+        context.queue,
+        gradient_pipeline.pipeline(),
+        gradient_pipeline.pipeline_layout(),
+        gradient_pipeline.descriptor_set(),
+        width,
+        height,
+        swapchain.swap_rb(),
+    )?;
 
-    let buffer = {
-        let create_info = vk::BufferCreateInfo::builder()
-            .size(value_count * std::mem::size_of::<i32>() as vk::DeviceSize)
-            .usage(vk::BufferUsageFlags::TRANSFER_DST);
-        unsafe { device.create_buffer(&create_info, None) }.unwrap()
-    };
+    let frame_sync = FrameSync::new(&context)?;
 
-    let allocation = {
-        let memory_requirements = unsafe { device.get_buffer_memory_requirements(buffer) };
-
-        let allocation_create_description = AllocationCreateDesc {
-            name: "Buffer Allocation",
-            requirements: memory_requirements,
-            location: gpu_allocator::MemoryLocation::GpuToCpu,
-            linear: true,
-            allocation_scheme: AllocationScheme::GpuAllocatorManaged,
-        };
-
-        let allocation = allocator.allocate(&allocation_create_description)?;
-        unsafe { device.bind_buffer_memory(buffer, allocation.memory(), allocation.offset()) }
-            .unwrap();
-        allocation
-    };
+    let mut show_fps = true;
+    let mut frame_count = 0u32;
+    let mut fps_timer = std::time::Instant::now();
 
-    let command_pool = {
-        let create_info = vk::CommandPoolCreateInfo::builder().queue_family_index(0);
-        unsafe { device.create_command_pool(&create_info, None) }?
-    };
+    // `run_return` (rather than `EventLoop::run`, which never returns and
+    // never drops values its closure captured) is what lets the closure
+    // below borrow instead of `move`, so control comes back here once the
+    // window closes and the manual teardown and `Drop`s beneath it still run.
+    event_loop.run_return(|event, _, control_flow| {
+        *control_flow = ControlFlow::Poll;
+
+        match event {
+            Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } => {
+                *control_flow = ControlFlow::Exit;
+            }
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(VirtualKeyCode::F1),
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } => {
+                show_fps = !show_fps;
+                if !show_fps {
+                    window.set_title("vulkanrenderer");
+                }
+            }
+            Event::MainEventsCleared => {
+                if let Err(err) = present_frame(
+                    &device,
+                    context.queue,
+                    command_pool.command_pool(),
+                    &swapchain,
+                    pixel_buffer.buffer(),
+                    frame_sync.image_available(),
+                    frame_sync.render_finished(),
+                    frame_sync.in_flight_fence(),
+                ) {
+                    log::error!("present_frame failed: {err:#}");
+                    *control_flow = ControlFlow::Exit;
+                    return;
+                }
 
+                frame_count += 1;
+                let elapsed = fps_timer.elapsed();
+                if elapsed.as_secs_f32() >= 1.0 {
+                    if show_fps {
+                        window.set_title(&format!(
+                            "vulkanrenderer - {:.0} FPS",
+                            frame_count as f32 / elapsed.as_secs_f32()
+                        ));
+                    }
+                    frame_count = 0;
+                    fps_timer = std::time::Instant::now();
+                }
+            }
+            _ => {}
+        }
+    });
+
+    // `run_return` has returned control here (the window closed, or a frame
+    // failed to present); wait for the GPU to go idle, then let
+    // `frame_sync`/`command_pool`/`gradient_pipeline`/`swapchain`/
+    // `pixel_buffer`/`context` run their own `Drop` impls in reverse
+    // declaration order as this function returns — no handle here is
+    // without an RAII owner anymore.
+    unsafe { device.device_wait_idle() }?;
+
+    Ok(())
+}
+
+/// Dispatches the gradient compute shader once, writing the whole image
+/// into `descriptor_set`'s bound buffer, and waits for it to finish.
+#[allow(clippy::too_many_arguments)]
+fn run_gradient_compute(
+    device: &ash::Device,
+    command_pool: vk::CommandPool,
+    queue: vk::Queue,
+    pipeline: vk::Pipeline,
+    pipeline_layout: vk::PipelineLayout,
+    descriptor_set: vk::DescriptorSet,
+    width: u32,
+    height: u32,
+    swap_rb: bool,
+) -> Result<()> {
     let command_buffer = {
         let create_info = vk::CommandBufferAllocateInfo::builder()
             .level(vk::CommandBufferLevel::PRIMARY)
@@ -91,67 +184,216 @@ fn main() -> Result<()> {
             .context("Allocation of Command Buffer failed!")?
     };
 
-    // RECORDING COMMAND BUFFER
     {
-        let begin_info = vk::CommandBufferBeginInfo::builder();
+        let begin_info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
         unsafe { device.begin_command_buffer(command_buffer, &begin_info) }?
     }
 
     unsafe {
-        device.cmd_fill_buffer(
+        device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::COMPUTE, pipeline);
+        device.cmd_bind_descriptor_sets(
             command_buffer,
-            buffer,
-            allocation.offset(),
-            allocation.size(),
-            value,
-        )
+            vk::PipelineBindPoint::COMPUTE,
+            pipeline_layout,
+            0,
+            std::slice::from_ref(&descriptor_set),
+            &[],
+        );
+        let push_constants = [width, height, swap_rb as u32];
+        let push_constant_bytes = std::slice::from_raw_parts(
+            push_constants.as_ptr() as *const u8,
+            std::mem::size_of_val(&push_constants),
+        );
+        device.cmd_push_constants(
+            command_buffer,
+            pipeline_layout,
+            vk::ShaderStageFlags::COMPUTE,
+            0,
+            push_constant_bytes,
+        );
+
+        const WORKGROUP_SIZE: u32 = 8;
+        let group_count_x = (width + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE;
+        let group_count_y = (height + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE;
+        device.cmd_dispatch(command_buffer, group_count_x, group_count_y, 1);
     }
 
     unsafe { device.end_command_buffer(command_buffer) }?;
 
-    let fence = {
-        let create_info = vk::FenceCreateInfo::builder().build();
-        unsafe { device.create_fence(&create_info, None) }?
+    let fence = create_fence(device, vk::FenceCreateFlags::empty())?;
+    let submit_info =
+        vk::SubmitInfo::builder().command_buffers(std::slice::from_ref(&command_buffer));
+    unsafe { device.queue_submit(queue, std::slice::from_ref(&submit_info), fence) }?;
+    unsafe { device.wait_for_fences(std::slice::from_ref(&fence), true, u64::MAX) }?;
+
+    unsafe { device.destroy_fence(fence, None) }
+    unsafe { device.free_command_buffers(command_pool, std::slice::from_ref(&command_buffer)) }
+
+    Ok(())
+}
+
+/// Acquires the next swapchain image, copies `pixel_buffer` into it, and
+/// presents it, using `image_available`/`render_finished` as the
+/// acquire/submit/present semaphores and `in_flight_fence` to keep a
+/// single frame in flight at a time.
+#[allow(clippy::too_many_arguments)]
+fn present_frame(
+    device: &ash::Device,
+    queue: vk::Queue,
+    command_pool: vk::CommandPool,
+    swapchain: &Swapchain,
+    pixel_buffer: vk::Buffer,
+    image_available: vk::Semaphore,
+    render_finished: vk::Semaphore,
+    in_flight_fence: vk::Fence,
+) -> Result<()> {
+    unsafe { device.wait_for_fences(std::slice::from_ref(&in_flight_fence), true, u64::MAX) }?;
+
+    let acquire_result = unsafe {
+        swapchain.loader().acquire_next_image(
+            swapchain.swapchain(),
+            u64::MAX,
+            image_available,
+            vk::Fence::null(),
+        )
+    };
+    // The window is fixed-size (see `with_resizable(false)` in `main`), so
+    // the swapchain's extent should never go stale; still guard the one
+    // error the spec allows regardless (e.g. the window is moved to a
+    // monitor with a different format) rather than unwrapping into a panic.
+    let (image_index, _suboptimal) = match acquire_result {
+        Ok(result) => result,
+        Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+            log::warn!("swapchain out of date on acquire, skipping frame");
+            return Ok(());
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    // Only reset the fence once we know this frame will actually submit;
+    // resetting it before a skipped frame above would leave it unsignaled
+    // with nothing left to signal it, deadlocking the next frame's wait.
+    unsafe { device.reset_fences(std::slice::from_ref(&in_flight_fence)) }?;
+
+    let image = swapchain.image(image_index as usize);
+    let extent = swapchain.extent();
+
+    let command_buffer = {
+        let create_info = vk::CommandBufferAllocateInfo::builder()
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_pool(command_pool)
+            .command_buffer_count(1);
+        unsafe { device.allocate_command_buffers(&create_info) }?
+            .into_iter()
+            .next()
+            .context("Allocation of Command Buffer failed!")?
     };
 
-    // EXECUTE THE COMMAND BUFFER BY UPLOADING IT TO THE GPU THROUGH THE QUEUE
     {
-        let submit_info =
-            vk::SubmitInfo::builder().command_buffers(std::slice::from_ref(&command_buffer));
-        unsafe {
-            device
-                .queue_submit(queue, std::slice::from_ref(&submit_info), fence)
-                .unwrap()
-        };
+        let begin_info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        unsafe { device.begin_command_buffer(command_buffer, &begin_info) }?
+    }
+
+    let subresource_range = vk::ImageSubresourceRange {
+        aspect_mask: vk::ImageAspectFlags::COLOR,
+        base_mip_level: 0,
+        level_count: 1,
+        base_array_layer: 0,
+        layer_count: 1,
     };
 
-    // WAIT FOR THE EXECUTION TO COMPLETE
     unsafe {
-        device.wait_for_fences(std::slice::from_ref(&fence), true, u64::MAX)?;
+        let to_transfer_dst = vk::ImageMemoryBarrier::builder()
+            .old_layout(vk::ImageLayout::UNDEFINED)
+            .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(subresource_range)
+            .dst_access_mask(vk::AccessFlags::TRANSFER_WRITE);
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            std::slice::from_ref(&to_transfer_dst),
+        );
+
+        let region = vk::BufferImageCopy::builder()
+            .image_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .image_extent(vk::Extent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            });
+        device.cmd_copy_buffer_to_image(
+            command_buffer,
+            pixel_buffer,
+            image,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            std::slice::from_ref(&region),
+        );
+
+        let to_present = vk::ImageMemoryBarrier::builder()
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(vk::ImageLayout::PRESENT_SRC_KHR)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(subresource_range)
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE);
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            std::slice::from_ref(&to_present),
+        );
     }
 
-    let data = allocation
-        .mapped_slice()
-        .context("Cannot Able to Access Buffer from Host")?;
-
-    // READ THE DATA
-    image::save_buffer(
-        "image.png",
-        data,
-        width as u32,
-        height as u32,
-        image::ColorType::Rgba8,
-    )?;
+    unsafe { device.end_command_buffer(command_buffer) }?;
 
-    // CLEAN UP
-    unsafe { device.destroy_fence(fence, None) }
-    unsafe { device.destroy_command_pool(command_pool, None) }
+    let wait_stages = [vk::PipelineStageFlags::TRANSFER];
+    let submit_info = vk::SubmitInfo::builder()
+        .wait_semaphores(std::slice::from_ref(&image_available))
+        .wait_dst_stage_mask(&wait_stages)
+        .command_buffers(std::slice::from_ref(&command_buffer))
+        .signal_semaphores(std::slice::from_ref(&render_finished));
+    unsafe {
+        device.queue_submit(
+            queue,
+            std::slice::from_ref(&submit_info),
+            in_flight_fence,
+        )
+    }?;
+
+    let swapchains = [swapchain.swapchain()];
+    let image_indices = [image_index];
+    let present_info = vk::PresentInfoKHR::builder()
+        .wait_semaphores(std::slice::from_ref(&render_finished))
+        .swapchains(&swapchains)
+        .image_indices(&image_indices);
+    match unsafe { swapchain.loader().queue_present(queue, &present_info) } {
+        Ok(_suboptimal) => {}
+        Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+            log::warn!("swapchain out of date on present, skipping frame");
+        }
+        Err(err) => return Err(err.into()),
+    }
 
-    allocator.free(allocation).unwrap();
-    drop(allocator);
-    unsafe { device.destroy_buffer(buffer, None) }
+    unsafe { device.wait_for_fences(std::slice::from_ref(&in_flight_fence), true, u64::MAX) }?;
+    unsafe { device.free_command_buffers(command_pool, std::slice::from_ref(&command_buffer)) }
 
-    unsafe { device.destroy_device(None) }
-    unsafe { instance.destroy_instance(None) }
     Ok(())
 }
@@ -0,0 +1,239 @@
+use anyhow::{Context, Result};
+use ash::vk;
+
+use crate::buffer::ScopedBuffer;
+use crate::vulkan_context::VulkanContext;
+
+struct DescriptorSetLayout {
+    device: ash::Device,
+    handle: vk::DescriptorSetLayout,
+}
+
+impl DescriptorSetLayout {
+    fn new(context: &VulkanContext) -> Result<Self> {
+        let binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::COMPUTE);
+        let create_info =
+            vk::DescriptorSetLayoutCreateInfo::builder().bindings(std::slice::from_ref(&binding));
+        let handle = unsafe { context.device.create_descriptor_set_layout(&create_info, None) }?;
+        Ok(Self {
+            device: context.device.clone(),
+            handle,
+        })
+    }
+}
+
+impl Drop for DescriptorSetLayout {
+    fn drop(&mut self) {
+        unsafe { self.device.destroy_descriptor_set_layout(self.handle, None) }
+    }
+}
+
+struct PipelineLayout {
+    device: ash::Device,
+    handle: vk::PipelineLayout,
+}
+
+impl PipelineLayout {
+    fn new(context: &VulkanContext, descriptor_set_layout: vk::DescriptorSetLayout) -> Result<Self> {
+        let push_constant_range = vk::PushConstantRange::builder()
+            .stage_flags(vk::ShaderStageFlags::COMPUTE)
+            .offset(0)
+            .size(std::mem::size_of::<[u32; 3]>() as u32)
+            .build();
+        let create_info = vk::PipelineLayoutCreateInfo::builder()
+            .set_layouts(std::slice::from_ref(&descriptor_set_layout))
+            .push_constant_ranges(std::slice::from_ref(&push_constant_range));
+        let handle = unsafe { context.device.create_pipeline_layout(&create_info, None) }?;
+        Ok(Self {
+            device: context.device.clone(),
+            handle,
+        })
+    }
+}
+
+impl Drop for PipelineLayout {
+    fn drop(&mut self) {
+        unsafe { self.device.destroy_pipeline_layout(self.handle, None) }
+    }
+}
+
+struct ShaderModule {
+    device: ash::Device,
+    handle: vk::ShaderModule,
+}
+
+impl ShaderModule {
+    fn new(context: &VulkanContext) -> Result<Self> {
+        let spirv = include_bytes!(concat!(env!("OUT_DIR"), "/gradient.spv"));
+        let code = ash::util::read_spv(&mut std::io::Cursor::new(&spirv[..]))
+            .context("gradient.spv is not valid SPIR-V")?;
+        let create_info = vk::ShaderModuleCreateInfo::builder().code(&code);
+        let handle = unsafe { context.device.create_shader_module(&create_info, None) }?;
+        Ok(Self {
+            device: context.device.clone(),
+            handle,
+        })
+    }
+}
+
+impl Drop for ShaderModule {
+    fn drop(&mut self) {
+        unsafe { self.device.destroy_shader_module(self.handle, None) }
+    }
+}
+
+struct ComputePipeline {
+    device: ash::Device,
+    handle: vk::Pipeline,
+}
+
+impl ComputePipeline {
+    fn new(
+        context: &VulkanContext,
+        shader_module: vk::ShaderModule,
+        pipeline_layout: vk::PipelineLayout,
+    ) -> Result<Self> {
+        let entry_point = std::ffi::CString::new("main").unwrap();
+        let stage = vk::PipelineShaderStageCreateInfo::builder()
+            .stage(vk::ShaderStageFlags::COMPUTE)
+            .module(shader_module)
+            .name(&entry_point);
+        let create_info = vk::ComputePipelineCreateInfo::builder()
+            .stage(stage.build())
+            .layout(pipeline_layout);
+        let handle = unsafe {
+            context.device.create_compute_pipelines(
+                vk::PipelineCache::null(),
+                std::slice::from_ref(&create_info),
+                None,
+            )
+        }
+        .map_err(|(_, err)| err)?
+        .into_iter()
+        .next()
+        .context("Creation of Compute Pipeline failed!")?;
+        Ok(Self {
+            device: context.device.clone(),
+            handle,
+        })
+    }
+}
+
+impl Drop for ComputePipeline {
+    fn drop(&mut self) {
+        unsafe { self.device.destroy_pipeline(self.handle, None) }
+    }
+}
+
+struct DescriptorPool {
+    device: ash::Device,
+    handle: vk::DescriptorPool,
+}
+
+impl DescriptorPool {
+    fn new(context: &VulkanContext) -> Result<Self> {
+        let pool_size = vk::DescriptorPoolSize::builder()
+            .ty(vk::DescriptorType::STORAGE_BUFFER)
+            .descriptor_count(1);
+        let create_info = vk::DescriptorPoolCreateInfo::builder()
+            .pool_sizes(std::slice::from_ref(&pool_size))
+            .max_sets(1);
+        let handle = unsafe { context.device.create_descriptor_pool(&create_info, None) }?;
+        Ok(Self {
+            device: context.device.clone(),
+            handle,
+        })
+    }
+}
+
+impl Drop for DescriptorPool {
+    fn drop(&mut self) {
+        unsafe { self.device.destroy_descriptor_pool(self.handle, None) }
+    }
+}
+
+/// Owns the gradient compute pipeline and all the descriptor machinery
+/// around it (set layout, pipeline layout, shader module, descriptor
+/// pool/set) and tears all of it down on drop.
+///
+/// Each piece above is its own tiny RAII wrapper, so if `new` fails
+/// partway through — shader compilation, pipeline creation, whatever —
+/// the pieces already built are cleaned up by their own `Drop` impls as
+/// this function unwinds, the same way any other early `?` return here
+/// would be; nothing is left leaked for callers to clean up by hand.
+pub struct GradientPipeline {
+    descriptor_set_layout: DescriptorSetLayout,
+    pipeline_layout: PipelineLayout,
+    // Held only for its `Drop` impl; nothing needs the handle back out
+    // once the pipeline that was built from it exists.
+    #[allow(dead_code)]
+    shader_module: ShaderModule,
+    pipeline: ComputePipeline,
+    // Held only for its `Drop` impl; the descriptor set it allocated is
+    // exposed directly via `descriptor_set()` instead.
+    #[allow(dead_code)]
+    descriptor_pool: DescriptorPool,
+    descriptor_set: vk::DescriptorSet,
+}
+
+impl GradientPipeline {
+    pub fn new(context: &VulkanContext, pixel_buffer: &ScopedBuffer) -> Result<Self> {
+        let descriptor_set_layout = DescriptorSetLayout::new(context)?;
+        let pipeline_layout = PipelineLayout::new(context, descriptor_set_layout.handle)?;
+        let shader_module = ShaderModule::new(context)?;
+        let pipeline = ComputePipeline::new(context, shader_module.handle, pipeline_layout.handle)?;
+        let descriptor_pool = DescriptorPool::new(context)?;
+
+        let descriptor_set = {
+            let create_info = vk::DescriptorSetAllocateInfo::builder()
+                .descriptor_pool(descriptor_pool.handle)
+                .set_layouts(std::slice::from_ref(&descriptor_set_layout.handle));
+            unsafe { context.device.allocate_descriptor_sets(&create_info) }?
+                .into_iter()
+                .next()
+                .context("Allocation of Descriptor Set failed!")?
+        };
+
+        {
+            let buffer_info = vk::DescriptorBufferInfo::builder()
+                .buffer(pixel_buffer.buffer())
+                .offset(0)
+                .range(vk::WHOLE_SIZE);
+            let write = vk::WriteDescriptorSet::builder()
+                .dst_set(descriptor_set)
+                .dst_binding(0)
+                .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                .buffer_info(std::slice::from_ref(&buffer_info));
+            unsafe {
+                context
+                    .device
+                    .update_descriptor_sets(std::slice::from_ref(&write), &[])
+            }
+        }
+
+        Ok(Self {
+            descriptor_set_layout,
+            pipeline_layout,
+            shader_module,
+            pipeline,
+            descriptor_pool,
+            descriptor_set,
+        })
+    }
+
+    pub fn pipeline(&self) -> vk::Pipeline {
+        self.pipeline.handle
+    }
+
+    pub fn pipeline_layout(&self) -> vk::PipelineLayout {
+        self.pipeline_layout.handle
+    }
+
+    pub fn descriptor_set(&self) -> vk::DescriptorSet {
+        self.descriptor_set
+    }
+}
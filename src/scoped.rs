@@ -0,0 +1,105 @@
+use anyhow::Result;
+use ash::vk;
+
+use crate::vulkan_context::VulkanContext;
+
+/// An RAII command pool: owns a `vk::CommandPool` and destroys it on drop.
+pub struct ScopedCommandPool {
+    device: ash::Device,
+    command_pool: vk::CommandPool,
+}
+
+impl ScopedCommandPool {
+    pub fn new(context: &VulkanContext) -> Result<Self> {
+        let create_info =
+            vk::CommandPoolCreateInfo::builder().queue_family_index(context.queue_family_index);
+        let command_pool = unsafe { context.device.create_command_pool(&create_info, None) }?;
+        Ok(Self {
+            device: context.device.clone(),
+            command_pool,
+        })
+    }
+
+    pub fn command_pool(&self) -> vk::CommandPool {
+        self.command_pool
+    }
+}
+
+impl Drop for ScopedCommandPool {
+    fn drop(&mut self) {
+        unsafe { self.device.destroy_command_pool(self.command_pool, None) }
+    }
+}
+
+pub(crate) fn create_semaphore(device: &ash::Device) -> Result<vk::Semaphore> {
+    let create_info = vk::SemaphoreCreateInfo::builder();
+    Ok(unsafe { device.create_semaphore(&create_info, None) }?)
+}
+
+pub(crate) fn create_fence(device: &ash::Device, flags: vk::FenceCreateFlags) -> Result<vk::Fence> {
+    let create_info = vk::FenceCreateInfo::builder().flags(flags);
+    Ok(unsafe { device.create_fence(&create_info, None) }?)
+}
+
+/// An RAII frame-sync bundle: the acquire/render semaphore pair and the
+/// in-flight fence `present_frame` waits on, so a single frame's worth of
+/// sync state can't outlive a failed construction or the window closing.
+pub struct FrameSync {
+    device: ash::Device,
+    image_available: vk::Semaphore,
+    render_finished: vk::Semaphore,
+    in_flight_fence: vk::Fence,
+}
+
+impl FrameSync {
+    pub fn new(context: &VulkanContext) -> Result<Self> {
+        let device = context.device.clone();
+        let image_available = create_semaphore(&device)?;
+        let render_finished = match create_semaphore(&device) {
+            Ok(semaphore) => semaphore,
+            Err(err) => {
+                unsafe { device.destroy_semaphore(image_available, None) }
+                return Err(err);
+            }
+        };
+        let in_flight_fence = match create_fence(&device, vk::FenceCreateFlags::SIGNALED) {
+            Ok(fence) => fence,
+            Err(err) => {
+                unsafe {
+                    device.destroy_semaphore(image_available, None);
+                    device.destroy_semaphore(render_finished, None);
+                }
+                return Err(err);
+            }
+        };
+
+        Ok(Self {
+            device,
+            image_available,
+            render_finished,
+            in_flight_fence,
+        })
+    }
+
+    pub fn image_available(&self) -> vk::Semaphore {
+        self.image_available
+    }
+
+    pub fn render_finished(&self) -> vk::Semaphore {
+        self.render_finished
+    }
+
+    pub fn in_flight_fence(&self) -> vk::Fence {
+        self.in_flight_fence
+    }
+}
+
+impl Drop for FrameSync {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_semaphore(self.image_available, None);
+            self.device.destroy_semaphore(self.render_finished, None);
+            self.device.destroy_fence(self.in_flight_fence, None);
+        }
+    }
+}
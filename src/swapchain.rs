@@ -0,0 +1,165 @@
+use anyhow::{Context, Result};
+use ash::vk;
+
+use crate::vulkan_context::VulkanContext;
+
+/// Owns the `VK_KHR_swapchain` swapchain plus its image views, and tears
+/// both down on drop. Presentation always uses `PresentModeKHR::FIFO`
+/// since it's guaranteed to be supported and gives us simple vsync'd
+/// presentation without tearing.
+pub struct Swapchain {
+    device: ash::Device,
+    loader: ash::extensions::khr::Swapchain,
+    swapchain: vk::SwapchainKHR,
+    images: Vec<vk::Image>,
+    image_views: Vec<vk::ImageView>,
+    extent: vk::Extent2D,
+    swap_rb: bool,
+}
+
+impl Swapchain {
+    pub fn new(context: &VulkanContext, width: u32, height: u32) -> Result<Self> {
+        let surface_capabilities = unsafe {
+            context
+                .surface_loader
+                .get_physical_device_surface_capabilities(context.physical_device, context.surface)
+        }?;
+        let surface_formats = unsafe {
+            context
+                .surface_loader
+                .get_physical_device_surface_formats(context.physical_device, context.surface)
+        }?;
+
+        // R8G8B8A8 matches the byte layout the gradient compute shader
+        // writes into the pixel buffer; B8G8R8A8 is what most desktop
+        // Vulkan drivers (NVIDIA/AMD on Windows, many Linux drivers)
+        // actually report, so it's tried as an equally-first-class option
+        // rather than a fallback — `swap_rb` below tells the shader to
+        // write channels in whichever order this surface needs. Only an
+        // outright unsupported surface falls through to "take whatever's
+        // first and hope", which can still present with swapped colors.
+        let surface_format = surface_formats
+            .iter()
+            .find(|format| {
+                format.format == vk::Format::R8G8B8A8_UNORM
+                    && format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
+            })
+            .or_else(|| {
+                surface_formats.iter().find(|format| {
+                    format.format == vk::Format::B8G8R8A8_UNORM
+                        && format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
+                })
+            })
+            .or_else(|| {
+                log::warn!(
+                    "surface exposes neither R8G8B8A8_UNORM nor B8G8R8A8_UNORM, \
+                     colors may come out wrong"
+                );
+                surface_formats.first()
+            })
+            .context("Surface exposes no formats!")?;
+        let swap_rb = surface_format.format == vk::Format::B8G8R8A8_UNORM;
+
+        let extent = if surface_capabilities.current_extent.width != u32::MAX {
+            surface_capabilities.current_extent
+        } else {
+            vk::Extent2D {
+                width: width.clamp(
+                    surface_capabilities.min_image_extent.width,
+                    surface_capabilities.max_image_extent.width,
+                ),
+                height: height.clamp(
+                    surface_capabilities.min_image_extent.height,
+                    surface_capabilities.max_image_extent.height,
+                ),
+            }
+        };
+
+        let image_count = if surface_capabilities.max_image_count == 0 {
+            surface_capabilities.min_image_count + 1
+        } else {
+            (surface_capabilities.min_image_count + 1).min(surface_capabilities.max_image_count)
+        };
+
+        let loader = ash::extensions::khr::Swapchain::new(&context.instance, &context.device);
+        let swapchain = {
+            let create_info = vk::SwapchainCreateInfoKHR::builder()
+                .surface(context.surface)
+                .min_image_count(image_count)
+                .image_format(surface_format.format)
+                .image_color_space(surface_format.color_space)
+                .image_extent(extent)
+                .image_array_layers(1)
+                .image_usage(vk::ImageUsageFlags::TRANSFER_DST)
+                .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
+                .pre_transform(surface_capabilities.current_transform)
+                .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+                .present_mode(vk::PresentModeKHR::FIFO)
+                .clipped(true);
+            unsafe { loader.create_swapchain(&create_info, None) }?
+        };
+
+        let images = unsafe { loader.get_swapchain_images(swapchain) }?;
+
+        let image_views = images
+            .iter()
+            .map(|&image| {
+                let create_info = vk::ImageViewCreateInfo::builder()
+                    .image(image)
+                    .view_type(vk::ImageViewType::TYPE_2D)
+                    .format(surface_format.format)
+                    .subresource_range(vk::ImageSubresourceRange {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        base_mip_level: 0,
+                        level_count: 1,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    });
+                unsafe { context.device.create_image_view(&create_info, None) }
+                    .context("Creation of Swapchain Image View failed!")
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            device: context.device.clone(),
+            loader,
+            swapchain,
+            images,
+            image_views,
+            extent,
+            swap_rb,
+        })
+    }
+
+    pub fn swapchain(&self) -> vk::SwapchainKHR {
+        self.swapchain
+    }
+
+    pub fn loader(&self) -> &ash::extensions::khr::Swapchain {
+        &self.loader
+    }
+
+    pub fn image(&self, index: usize) -> vk::Image {
+        self.images[index]
+    }
+
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    /// Whether the chosen surface format is `B8G8R8A8_UNORM` rather than
+    /// `R8G8B8A8_UNORM`, so callers filling the pixel buffer know to write
+    /// red and blue swapped.
+    pub fn swap_rb(&self) -> bool {
+        self.swap_rb
+    }
+}
+
+impl Drop for Swapchain {
+    fn drop(&mut self) {
+        for &image_view in &self.image_views {
+            unsafe { self.device.destroy_image_view(image_view, None) }
+        }
+        unsafe { self.loader.destroy_swapchain(self.swapchain, None) }
+    }
+}
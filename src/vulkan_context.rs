@@ -0,0 +1,275 @@
+use std::cell::RefCell;
+use std::mem::ManuallyDrop;
+use std::rc::Rc;
+
+use anyhow::{Context, Result};
+use ash::vk::{self, DeviceQueueCreateInfo};
+use gpu_allocator::vulkan::*;
+use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
+
+const VALIDATION_LAYER: &std::ffi::CStr =
+    unsafe { std::ffi::CStr::from_bytes_with_nul_unchecked(b"VK_LAYER_KHRONOS_validation\0") };
+
+/// Validation is opt-in via `--validation`, or on by default in debug builds.
+fn validation_requested() -> bool {
+    cfg!(debug_assertions) || std::env::args().any(|arg| arg == "--validation")
+}
+
+/// Returns `VALIDATION_LAYER` if both requested and supported by the
+/// installed Vulkan loader, so callers can silently skip it rather than
+/// fail hard on machines without the LunarG SDK installed.
+fn available_validation_layer(entry: &ash::Entry) -> Result<Option<&'static std::ffi::CStr>> {
+    if !validation_requested() {
+        return Ok(None);
+    }
+
+    let supported = unsafe { entry.enumerate_instance_layer_properties() }?
+        .iter()
+        .any(|layer| {
+            let name = unsafe { std::ffi::CStr::from_ptr(layer.layer_name.as_ptr()) };
+            name == VALIDATION_LAYER
+        });
+
+    if !supported {
+        log::warn!("VK_LAYER_KHRONOS_validation requested but not available, continuing without it");
+        return Ok(None);
+    }
+
+    Ok(Some(VALIDATION_LAYER))
+}
+
+unsafe extern "system" fn debug_messenger_callback(
+    severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    _message_type: vk::DebugUtilsMessageTypeFlagsEXT,
+    callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
+    _user_data: *mut std::ffi::c_void,
+) -> vk::Bool32 {
+    let message = std::ffi::CStr::from_ptr((*callback_data).p_message).to_string_lossy();
+
+    match severity {
+        vk::DebugUtilsMessageSeverityFlagsEXT::ERROR => log::error!("{message}"),
+        vk::DebugUtilsMessageSeverityFlagsEXT::WARNING => log::warn!("{message}"),
+        vk::DebugUtilsMessageSeverityFlagsEXT::INFO => log::info!("{message}"),
+        _ => log::debug!("{message}"),
+    }
+
+    vk::FALSE
+}
+
+fn debug_messenger_create_info() -> vk::DebugUtilsMessengerCreateInfoEXT {
+    vk::DebugUtilsMessengerCreateInfoEXT::builder()
+        .message_severity(
+            vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
+                | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
+                | vk::DebugUtilsMessageSeverityFlagsEXT::INFO,
+        )
+        .message_type(
+            vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
+                | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
+        )
+        .pfn_user_callback(Some(debug_messenger_callback))
+        .build()
+}
+
+/// Picks a physical device and a queue family capable of both compute work
+/// and presenting to `surface`.
+///
+/// Candidates are ranked by device type (discrete GPU first) so that on
+/// machines with both an integrated and a discrete GPU, e.g. laptops, the
+/// discrete one is preferred. Within each device, the first queue family
+/// advertising `COMPUTE` (falling back to `TRANSFER`) that also reports
+/// `vkGetPhysicalDeviceSurfaceSupportKHR` for `surface` is selected, rather
+/// than assuming family `0` supports the operations we need.
+fn select_physical_device(
+    instance: &ash::Instance,
+    surface_loader: &ash::extensions::khr::Surface,
+    surface: vk::SurfaceKHR,
+) -> Result<(vk::PhysicalDevice, u32)> {
+    let mut candidates = unsafe { instance.enumerate_physical_devices() }?
+        .into_iter()
+        .filter_map(|physical_device| {
+            let queue_families =
+                unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
+            let find_family = |flags: vk::QueueFlags| {
+                queue_families
+                    .iter()
+                    .enumerate()
+                    .find(|(index, properties)| {
+                        let supports_present = unsafe {
+                            surface_loader.get_physical_device_surface_support(
+                                physical_device,
+                                *index as u32,
+                                surface,
+                            )
+                        }
+                        .unwrap_or(false);
+                        properties.queue_flags.contains(flags) && supports_present
+                    })
+                    .map(|(index, _)| index as u32)
+            };
+            let queue_family_index = find_family(vk::QueueFlags::COMPUTE)
+                .or_else(|| find_family(vk::QueueFlags::TRANSFER))?;
+
+            let properties = unsafe { instance.get_physical_device_properties(physical_device) };
+            Some((physical_device, queue_family_index, properties.device_type))
+        })
+        .collect::<Vec<_>>();
+
+    candidates.sort_by_key(|(_, _, device_type)| device_type_rank(*device_type));
+
+    candidates
+        .into_iter()
+        .next()
+        .map(|(physical_device, queue_family_index, _)| (physical_device, queue_family_index))
+        .context("No physical device with a compute- and present-capable queue family found!")
+}
+
+/// Lower is preferred. Discrete GPUs win, followed by integrated, virtual,
+/// CPU and anything unclassified.
+fn device_type_rank(device_type: vk::PhysicalDeviceType) -> u32 {
+    match device_type {
+        vk::PhysicalDeviceType::DISCRETE_GPU => 0,
+        vk::PhysicalDeviceType::INTEGRATED_GPU => 1,
+        vk::PhysicalDeviceType::VIRTUAL_GPU => 2,
+        vk::PhysicalDeviceType::CPU => 3,
+        _ => 4,
+    }
+}
+
+/// Owns the core Vulkan/allocator handles (instance, device, queue,
+/// allocator) and tears them down in reverse order on drop, so an early
+/// `?` return can never leak them the way the old flat `main()` could.
+pub struct VulkanContext {
+    pub entry: ash::Entry,
+    pub instance: ash::Instance,
+    pub physical_device: vk::PhysicalDevice,
+    pub device: ash::Device,
+    pub queue: vk::Queue,
+    pub queue_family_index: u32,
+    pub allocator: ManuallyDrop<Rc<RefCell<Allocator>>>,
+    pub surface_loader: ash::extensions::khr::Surface,
+    pub surface: vk::SurfaceKHR,
+    debug_utils_loader: Option<ash::extensions::ext::DebugUtils>,
+    debug_messenger: Option<vk::DebugUtilsMessengerEXT>,
+}
+
+impl VulkanContext {
+    pub fn new(window: &winit::window::Window) -> Result<Self> {
+        let entry = unsafe { ash::Entry::load() }?;
+
+        let validation_layer = available_validation_layer(&entry)?;
+        let layer_names = validation_layer
+            .map(|layer| [layer.as_ptr()])
+            .unwrap_or_default();
+
+        let mut instance_extension_names =
+            ash_window::enumerate_required_extensions(window.raw_display_handle())?.to_vec();
+        if validation_layer.is_some() {
+            instance_extension_names.push(ash::extensions::ext::DebugUtils::name().as_ptr());
+        }
+
+        let mut messenger_create_info = debug_messenger_create_info();
+
+        let instance = {
+            let application_info =
+                vk::ApplicationInfo::builder().api_version(vk::API_VERSION_1_3);
+            let mut create_info = vk::InstanceCreateInfo::builder()
+                .application_info(&application_info)
+                .enabled_layer_names(if validation_layer.is_some() {
+                    &layer_names
+                } else {
+                    &[]
+                })
+                .enabled_extension_names(&instance_extension_names);
+            if validation_layer.is_some() {
+                create_info = create_info.push_next(&mut messenger_create_info);
+            }
+            unsafe { entry.create_instance(&create_info, None) }?
+        };
+
+        let debug_utils_loader = ash::extensions::ext::DebugUtils::new(&entry, &instance);
+        let debug_messenger = if validation_layer.is_some() {
+            Some(unsafe {
+                debug_utils_loader.create_debug_utils_messenger(&messenger_create_info, None)
+            }?)
+        } else {
+            None
+        };
+
+        let surface = unsafe {
+            ash_window::create_surface(
+                &entry,
+                &instance,
+                window.raw_display_handle(),
+                window.raw_window_handle(),
+                None,
+            )
+        }?;
+        let surface_loader = ash::extensions::khr::Surface::new(&entry, &instance);
+
+        let (physical_device, queue_family_index) =
+            select_physical_device(&instance, &surface_loader, surface)?;
+
+        let device = {
+            let queue_priorities = [1.0];
+            let queue_create_info = DeviceQueueCreateInfo::builder()
+                .queue_family_index(queue_family_index)
+                .queue_priorities(&queue_priorities);
+            let device_extension_names = [ash::extensions::khr::Swapchain::name().as_ptr()];
+            let create_info = vk::DeviceCreateInfo::builder()
+                .queue_create_infos(std::slice::from_ref(&queue_create_info))
+                .enabled_extension_names(&device_extension_names);
+            unsafe { instance.create_device(physical_device, &create_info, None) }?
+        };
+
+        let queue = unsafe { device.get_device_queue(queue_family_index, 0) };
+
+        let allocator = {
+            let allocator_create_desc = AllocatorCreateDesc {
+                instance: instance.clone(),
+                device: device.clone(),
+                physical_device,
+                debug_settings: Default::default(),
+                buffer_device_address: false,
+            };
+
+            Allocator::new(&allocator_create_desc)?
+        };
+
+        Ok(Self {
+            entry,
+            instance,
+            physical_device,
+            device,
+            queue,
+            queue_family_index,
+            allocator: ManuallyDrop::new(Rc::new(RefCell::new(allocator))),
+            surface_loader,
+            surface,
+            debug_utils_loader: validation_layer.is_some().then_some(debug_utils_loader),
+            debug_messenger,
+        })
+    }
+}
+
+impl Drop for VulkanContext {
+    fn drop(&mut self) {
+        unsafe {
+            // SAFETY: the allocator must be dropped before the device it was
+            // created from, and nothing touches `self.allocator` afterwards.
+            // This only releases the allocator's memory blocks immediately if no
+            // `ScopedBuffer` still holds a clone of the `Rc`; callers are
+            // expected to drop those before the context itself.
+            ManuallyDrop::drop(&mut self.allocator);
+            self.device.destroy_device(None);
+            self.surface_loader.destroy_surface(self.surface, None);
+            if let (Some(loader), Some(messenger)) =
+                (&self.debug_utils_loader, self.debug_messenger)
+            {
+                loader.destroy_debug_utils_messenger(messenger, None);
+            }
+            self.instance.destroy_instance(None);
+        }
+    }
+}